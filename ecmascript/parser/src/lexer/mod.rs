@@ -0,0 +1,523 @@
+pub(crate) mod state;
+
+use self::state::State;
+use crate::token::{BinOpToken, Error, Keyword, Token, TokenAndSpan, Word};
+use swc_common::{BytePos, Span};
+
+pub use self::state::{
+    Comment, CommentKind, DelimKind, LexError, LexSuggestion, Spacing, UnmatchedDelim,
+};
+
+/// Byte-level cursor the lexer reads from. Implemented once for plain
+/// `&str` source (see [`StringInput`]); kept generic so e.g. a future
+/// incremental-reparse input can plug in without touching the lexer itself.
+pub trait Input {
+    fn cur(&self) -> Option<char>;
+    fn peek(&self) -> Option<char>;
+    fn bump(&mut self);
+    fn cur_pos(&self) -> BytePos;
+    fn end_pos(&self) -> BytePos;
+    /// Source text between two positions previously observed via
+    /// `cur_pos`/`end_pos`.
+    fn slice(&self, from: BytePos, to: BytePos) -> &str;
+    /// Rewinds the cursor to a position previously observed via `cur_pos`,
+    /// so a [`Lexer`] can re-lex from there after a speculative parse (see
+    /// [`Lexer::rewind`]).
+    fn reset_to(&mut self, to: BytePos);
+}
+
+/// An [`Input`] over an in-memory `&str`.
+pub struct StringInput<'a> {
+    src: &'a str,
+    start_pos: BytePos,
+    pos: BytePos,
+}
+
+impl<'a> StringInput<'a> {
+    pub fn new(src: &'a str, start_pos: BytePos) -> Self {
+        StringInput {
+            src,
+            start_pos,
+            pos: start_pos,
+        }
+    }
+
+    fn idx(&self, pos: BytePos) -> usize {
+        (pos.0 - self.start_pos.0) as usize
+    }
+}
+
+impl<'a> Input for StringInput<'a> {
+    fn cur(&self) -> Option<char> {
+        self.src[self.idx(self.pos)..].chars().next()
+    }
+
+    fn peek(&self) -> Option<char> {
+        let mut chars = self.src[self.idx(self.pos)..].chars();
+        chars.next();
+        chars.next()
+    }
+
+    fn bump(&mut self) {
+        if let Some(c) = self.cur() {
+            self.pos = BytePos(self.pos.0 + c.len_utf8() as u32);
+        }
+    }
+
+    fn cur_pos(&self) -> BytePos {
+        self.pos
+    }
+
+    fn end_pos(&self) -> BytePos {
+        BytePos(self.start_pos.0 + self.src.len() as u32)
+    }
+
+    fn slice(&self, from: BytePos, to: BytePos) -> &str {
+        &self.src[self.idx(from)..self.idx(to)]
+    }
+
+    fn reset_to(&mut self, to: BytePos) {
+        self.pos = to;
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c == '_' || c == '$' || c.is_alphabetic()
+}
+
+fn is_ident_part(c: char) -> bool {
+    is_ident_start(c) || c.is_ascii_digit()
+}
+
+/// Tokenizer over an [`Input`].
+///
+/// Produces [`TokenAndSpan`]s via its `Iterator` impl (see `lexer::state`);
+/// speculative parses can snapshot/restore it with
+/// [`checkpoint`](Lexer::checkpoint)/[`rewind`](Lexer::rewind).
+pub struct Lexer<'a, I: Input> {
+    input: I,
+    pub(super) state: State,
+    _marker: std::marker::PhantomData<&'a ()>,
+}
+
+impl<'a, I: Input> Lexer<'a, I> {
+    pub fn new(input: I) -> Self {
+        Lexer {
+            input,
+            state: Default::default(),
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    pub(super) fn cur_pos(&self) -> BytePos {
+        self.input.cur_pos()
+    }
+
+    pub(super) fn span(&self, start: BytePos) -> Span {
+        Span::new(start, self.cur_pos())
+    }
+
+    pub(super) fn had_line_break_before_last(&self) -> bool {
+        self.state.had_line_break
+    }
+
+    /// Captures the lexer's current `State` and input position.
+    pub fn checkpoint(&self) -> state::Checkpoint {
+        state::Checkpoint {
+            state: self.state.clone(),
+            pos: self.cur_pos(),
+        }
+    }
+
+    /// Restores the lexer to a previously taken checkpoint, undoing any
+    /// tokens consumed since.
+    pub fn rewind(&mut self, checkpoint: state::Checkpoint) {
+        self.state = checkpoint.state;
+        self.input.reset_to(checkpoint.pos);
+    }
+
+    /// Skips whitespace and comments ahead of the next token, updating
+    /// `had_line_break` when a newline is crossed. When `capture_trivia` is
+    /// enabled, skipped comments are recorded as leading trivia of the token
+    /// that follows, or as trailing trivia of the previous token if no line
+    /// break separates them from it.
+    pub(super) fn skip_space(&mut self) -> Result<(), Error> {
+        let mut leading = Vec::new();
+        let mut past_line_break = false;
+
+        loop {
+            match self.input.cur() {
+                Some('\n') => {
+                    self.state.had_line_break = true;
+                    past_line_break = true;
+                    self.input.bump();
+                }
+                Some(c) if c.is_whitespace() => {
+                    self.input.bump();
+                }
+                Some('/') if self.input.peek() == Some('/') => {
+                    let comment = self.read_line_comment();
+                    self.queue_or_record_trailing(comment, past_line_break, &mut leading);
+                }
+                Some('/') if self.input.peek() == Some('*') => {
+                    let comment = self.read_block_comment()?;
+                    self.queue_or_record_trailing(comment, past_line_break, &mut leading);
+                }
+                _ => break,
+            }
+        }
+
+        if self.state.capture_trivia && !leading.is_empty() {
+            let token_start = self.cur_pos();
+            for comment in leading {
+                self.state.record_leading_comment(token_start, comment);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Queues `comment` as leading trivia of the upcoming token (there's no
+    /// token to trail yet, or a line break already separates it from the
+    /// previous one), or records it as trailing trivia of the previous token
+    /// otherwise.
+    fn queue_or_record_trailing(
+        &mut self,
+        comment: Comment,
+        past_line_break: bool,
+        leading: &mut Vec<Comment>,
+    ) {
+        if !self.state.capture_trivia {
+            return;
+        }
+
+        if past_line_break || self.state.is_first {
+            leading.push(comment);
+        } else {
+            let token_end = self.state.last_token_end;
+            self.state.record_trailing_comment(token_end, comment);
+        }
+    }
+
+    fn read_line_comment(&mut self) -> Comment {
+        let start = self.cur_pos();
+        self.input.bump(); // first `/`
+        self.input.bump(); // second `/`
+
+        while let Some(c) = self.input.cur() {
+            if c == '\n' {
+                break;
+            }
+            self.input.bump();
+        }
+
+        Comment {
+            kind: CommentKind::Line,
+            span: self.span(start),
+            text: self.input.slice(start, self.cur_pos()).to_owned(),
+        }
+    }
+
+    fn read_block_comment(&mut self) -> Result<Comment, Error> {
+        let start = self.cur_pos();
+        self.input.bump(); // `/`
+        self.input.bump(); // `*`
+
+        let is_jsdoc = self.input.cur() == Some('*') && self.input.peek() != Some('/');
+
+        loop {
+            match self.input.cur() {
+                None => {
+                    return Err(Error {
+                        span: self.span(start),
+                        message: "unterminated block comment".to_owned(),
+                    });
+                }
+                Some('\n') => {
+                    self.state.had_line_break = true;
+                    self.input.bump();
+                }
+                Some('*') if self.input.peek() == Some('/') => {
+                    self.input.bump();
+                    self.input.bump();
+                    break;
+                }
+                Some(_) => {
+                    self.input.bump();
+                }
+            }
+        }
+
+        Ok(Comment {
+            kind: if is_jsdoc {
+                CommentKind::JsDoc
+            } else {
+                CommentKind::Block
+            },
+            span: self.span(start),
+            text: self.input.slice(start, self.cur_pos()).to_owned(),
+        })
+    }
+
+    pub(super) fn read_token(&mut self) -> Result<Option<Token>, Error> {
+        let start = self.cur_pos();
+        let c = match self.input.cur() {
+            None => return Ok(None),
+            Some(c) => c,
+        };
+
+        let token = match c {
+            '(' => {
+                self.input.bump();
+                tok!('(')
+            }
+            ')' => {
+                self.input.bump();
+                tok!(')')
+            }
+            '{' => {
+                self.input.bump();
+                tok!('{')
+            }
+            '}' => {
+                self.input.bump();
+                tok!('}')
+            }
+            '`' => {
+                self.input.bump();
+                tok!('`')
+            }
+            '.' => {
+                self.input.bump();
+                Token::Dot
+            }
+            ':' => {
+                self.input.bump();
+                Token::Colon
+            }
+            ';' => {
+                self.input.bump();
+                Token::Semi
+            }
+            '?' => {
+                self.input.bump();
+                Token::Question
+            }
+            '<' => {
+                self.input.bump();
+                Token::BinOp(BinOpToken::Lt)
+            }
+            '>' => {
+                self.input.bump();
+                Token::BinOp(BinOpToken::Gt)
+            }
+            '+' if self.input.peek() == Some('+') => {
+                self.input.bump();
+                self.input.bump();
+                tok!("++")
+            }
+            '-' if self.input.peek() == Some('-') => {
+                self.input.bump();
+                self.input.bump();
+                tok!("--")
+            }
+            '$' if self.input.peek() == Some('{') => {
+                self.input.bump();
+                self.input.bump();
+                tok!("${")
+            }
+            '0'..='9' => self.read_number(),
+            c if is_ident_start(c) => self.read_word(),
+            _ => {
+                self.input.bump();
+                return Err(Error {
+                    span: self.span(start),
+                    message: format!("unexpected character `{}`", c),
+                });
+            }
+        };
+
+        Ok(Some(token))
+    }
+
+    pub(super) fn read_tmpl_token(&mut self, _tpl_start: BytePos) -> Result<Token, Error> {
+        let start = self.cur_pos();
+        while let Some(c) = self.input.cur() {
+            if c == '`' || (c == '$' && self.input.peek() == Some('{')) {
+                break;
+            }
+            self.input.bump();
+        }
+        let raw = self.input.slice(start, self.cur_pos()).to_owned();
+        Ok(Token::Template { raw })
+    }
+
+    fn read_word(&mut self) -> Token {
+        let start = self.cur_pos();
+        while let Some(c) = self.input.cur() {
+            if is_ident_part(c) {
+                self.input.bump();
+            } else {
+                break;
+            }
+        }
+        let word = self.input.slice(start, self.cur_pos()).to_owned();
+        match word.as_str() {
+            "function" => tok!("function"),
+            "of" => tok!("of"),
+            "if" => Token::Word(Word::Keyword(Keyword::If)),
+            "with" => Token::Word(Word::Keyword(Keyword::With)),
+            "while" => Token::Word(Word::Keyword(Keyword::While)),
+            "for" => Token::Word(Word::Keyword(Keyword::For)),
+            "return" => Token::Word(Word::Keyword(Keyword::Return)),
+            "yield" => Token::Word(Word::Keyword(Keyword::Yield)),
+            "else" => Token::Word(Word::Keyword(Keyword::Else)),
+            "let" => Token::Word(Word::Keyword(Keyword::Let)),
+            "const" => Token::Word(Word::Keyword(Keyword::Const)),
+            "var" => Token::Word(Word::Keyword(Keyword::Var)),
+            _ => Token::Word(Word::Ident(word)),
+        }
+    }
+
+    fn read_number(&mut self) -> Token {
+        let start = self.cur_pos();
+        let starts_with_zero = self.input.cur() == Some('0');
+
+        while let Some(c) = self.input.cur() {
+            if c.is_ascii_digit() {
+                self.input.bump();
+            } else {
+                break;
+            }
+        }
+
+        let raw = self.input.slice(start, self.cur_pos()).to_owned();
+
+        // A leading `0` followed by further digits (`0777`, `08`) is a
+        // legacy octal/decimal literal; `self.state.octal_pos` is resolved
+        // into a diagnostic by `State::check_legacy_octal`.
+        if starts_with_zero && raw.len() > 1 {
+            self.state.octal_pos = Some(start);
+        }
+
+        let span = self.span(start);
+        self.state.check_legacy_octal(span, &raw);
+
+        Token::Num { raw }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lex(src: &str) -> Vec<TokenAndSpan> {
+        let lexer = Lexer::new(StringInput::new(src, BytePos(0)));
+        lexer.collect()
+    }
+
+    #[test]
+    fn joint_spacing_for_optional_chaining() {
+        let tokens = lex("a?.b");
+        // `a`, `?`, `.`, `b`, all directly adjacent.
+        assert_eq!(tokens.len(), 4);
+        assert!(tokens[1..]
+            .iter()
+            .all(|t| t.spacing == Spacing::Joint));
+    }
+
+    #[test]
+    fn stray_closer_without_opener() {
+        let mut lexer = Lexer::new(StringInput::new("{}}", BytePos(0)));
+        let tokens: Vec<_> = (&mut lexer).collect();
+        assert_eq!(tokens.len(), 3);
+
+        let unmatched = lexer.state.take_unmatched_delims();
+        assert!(matches!(
+            unmatched.as_slice(),
+            [UnmatchedDelim::Stray {
+                found: DelimKind::Brace,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn checkpoint_rewind_relexes_same_tokens() {
+        let mut lexer = Lexer::new(StringInput::new("a.b.c", BytePos(0)));
+        lexer.next(); // consume `a`, leaving the checkpoint mid-stream.
+
+        let checkpoint = lexer.checkpoint();
+        let consumed: Vec<_> = (&mut lexer).collect();
+        assert_eq!(consumed.len(), 4); // `.`, `b`, `.`, `c`
+
+        lexer.rewind(checkpoint);
+        let relexed: Vec<_> = (&mut lexer).collect();
+
+        assert_eq!(consumed.len(), relexed.len());
+        for (before, after) in consumed.iter().zip(relexed.iter()) {
+            assert_eq!(before.token, after.token);
+        }
+    }
+
+    #[test]
+    fn captures_leading_line_comment() {
+        let mut lexer = Lexer::new(StringInput::new("// leading\na", BytePos(0)));
+        lexer.state.capture_trivia = true;
+        let tokens: Vec<_> = (&mut lexer).collect();
+        assert_eq!(tokens.len(), 1);
+
+        let leading = lexer.state.take_leading_comments(BytePos(11));
+        assert_eq!(leading.len(), 1);
+        assert_eq!(leading[0].kind, CommentKind::Line);
+        assert_eq!(leading[0].text, "// leading");
+    }
+
+    #[test]
+    fn captures_leading_jsdoc_comment_with_no_preceding_line_break() {
+        let mut lexer = Lexer::new(StringInput::new("/** jsdoc */b", BytePos(0)));
+        lexer.state.capture_trivia = true;
+        let tokens: Vec<_> = (&mut lexer).collect();
+        assert_eq!(tokens.len(), 1);
+
+        let leading = lexer.state.take_leading_comments(BytePos(12));
+        assert_eq!(leading.len(), 1);
+        assert_eq!(leading[0].kind, CommentKind::JsDoc);
+        assert_eq!(leading[0].text, "/** jsdoc */");
+    }
+
+    #[test]
+    fn legacy_octal_all_octal_digits_gets_0o_prefix() {
+        let mut lexer = Lexer::new(StringInput::new("0777", BytePos(0)));
+        let tokens: Vec<_> = (&mut lexer).collect();
+        assert_eq!(tokens.len(), 1);
+
+        let diagnostics = lexer.state.take_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        let suggestion = diagnostics[0].suggestion.as_ref().unwrap();
+        assert_eq!(suggestion.replacement, "0o777");
+    }
+
+    #[test]
+    fn legacy_octal_with_invalid_digit_strips_leading_zero() {
+        let mut lexer = Lexer::new(StringInput::new("08", BytePos(0)));
+        let tokens: Vec<_> = (&mut lexer).collect();
+        assert_eq!(tokens.len(), 1);
+
+        let diagnostics = lexer.state.take_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        let suggestion = diagnostics[0].suggestion.as_ref().unwrap();
+        assert_eq!(suggestion.replacement, "8");
+    }
+
+    #[test]
+    fn alone_spacing_across_whitespace() {
+        let tokens = lex("a ? .b");
+        // `a`, `?`, `.`, `b`; `?` and `.` are each preceded by a space, so
+        // both are `Alone`, while `b` directly follows `.` and is `Joint`.
+        assert_eq!(tokens.len(), 4);
+        assert_eq!(tokens[1].spacing, Spacing::Alone);
+        assert_eq!(tokens[2].spacing, Spacing::Alone);
+        assert_eq!(tokens[3].spacing, Spacing::Joint);
+    }
+}
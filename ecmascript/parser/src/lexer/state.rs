@@ -1,13 +1,21 @@
 use super::{Input, Lexer};
 use enum_kind::Kind;
 use smallvec::SmallVec;
-use swc_common::BytePos;
+use std::collections::HashMap;
+use std::mem;
+use swc_common::{BytePos, Span};
 use token::*;
 
 /// State of lexer.
 ///
 /// Ported from babylon.
-#[derive(Debug)]
+///
+/// `State` is `Clone`, so it can be snapshotted for speculative
+/// tokenization (see [`Lexer::checkpoint`]). Every field is either `Copy` or
+/// an owned collection (the context `SmallVec`, the delimiter and diagnostic
+/// `Vec`s, the trivia `HashMap`s); a clone copies all of them, so its cost
+/// grows with the comments and diagnostics captured and not yet drained.
+#[derive(Debug, Clone)]
 pub(super) struct State {
     pub is_expr_allowed: bool,
     pub octal_pos: Option<BytePos>,
@@ -19,6 +27,112 @@ pub(super) struct State {
     context: Context,
 
     token_type: Option<TokenType>,
+
+    /// Closing delimiters that didn't match their innermost opener, plus
+    /// openers that are still unclosed once EOF is reached.
+    ///
+    /// Populated by [`State::is_expr_allowed_on_next`] and
+    /// [`State::check_unclosed_delims`]; drained by the parser via
+    /// [`State::take_unmatched_delims`].
+    unmatched_delims: Vec<UnmatchedDelim>,
+
+    /// End position of the previously produced token. Used to derive
+    /// [`Spacing`] by byte contiguity in contexts where `skip_space` isn't
+    /// run (see [`State::can_skip_space`]).
+    last_token_end: BytePos,
+
+    /// When set, `skip_space` records comment trivia into
+    /// `leading_comments` / `trailing_comments` instead of discarding it, as
+    /// formatters and codemods need to round-trip comments. Off by default.
+    /// Does not affect the `can_skip_space()` gating or `Type::Tpl`
+    /// preserve-space logic, which stay untouched either way.
+    pub capture_trivia: bool,
+
+    /// Comments preceding the token that starts at a given `BytePos`, when
+    /// `capture_trivia` is enabled.
+    leading_comments: HashMap<BytePos, Vec<Comment>>,
+
+    /// Comments trailing the token that ends at a given `BytePos`, when
+    /// `capture_trivia` is enabled.
+    trailing_comments: HashMap<BytePos, Vec<Comment>>,
+
+    /// Recoverable diagnostics (deprecated-syntax warnings and the like)
+    /// accumulated during lexing, e.g. from [`State::check_legacy_octal`].
+    /// Drained by the parser via [`State::take_diagnostics`] rather than
+    /// surfaced as hard `Token::Error`s, so lexing can continue and tooling
+    /// can offer autofixes.
+    diagnostics: Vec<LexError>,
+}
+
+/// A machine-applicable rewrite suggestion.
+#[derive(Debug, Clone)]
+pub struct LexSuggestion {
+    pub span: Span,
+    pub replacement: String,
+}
+
+/// A recoverable lexer diagnostic, carrying enough information for tooling
+/// to both report the problem and offer an automatic fix.
+#[derive(Debug, Clone)]
+pub struct LexError {
+    pub span: Span,
+    pub message: &'static str,
+    pub suggestion: Option<LexSuggestion>,
+}
+
+/// Kind of comment trivia captured when [`State::capture_trivia`] is
+/// enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentKind {
+    Line,
+    Block,
+    /// A block comment of the form `/** ... */`.
+    JsDoc,
+}
+
+/// A single comment captured while trivia capture is enabled.
+#[derive(Debug, Clone)]
+pub struct Comment {
+    pub kind: CommentKind,
+    pub span: Span,
+    pub text: String,
+}
+
+/// Whether a token is immediately adjacent to the previous one, i.e. no
+/// whitespace or comment trivia separates them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Spacing {
+    /// Separated from the previous token by at least one byte of trivia.
+    /// The first token of the input is always `Alone`.
+    Alone,
+    /// Directly adjacent to the previous token.
+    Joint,
+}
+
+/// Kind of bracket an opener on the [`Context`] stack expects to be closed
+/// with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DelimKind {
+    Paren,
+    Brace,
+}
+
+/// A delimiter problem found while tracking the `Context` stack.
+#[derive(Debug, Clone, Copy)]
+pub enum UnmatchedDelim {
+    /// A closer whose innermost open delimiter is a different kind, e.g. a
+    /// `)` closing a `{`.
+    Mismatched {
+        open_pos: BytePos,
+        close_pos: BytePos,
+        expected: DelimKind,
+        found: DelimKind,
+    },
+    /// A closer with nothing at all left open to match, e.g. a stray `}`
+    /// after every real brace has already been closed.
+    Stray { close_pos: BytePos, found: DelimKind },
+    /// An opener still open once EOF is reached.
+    Unclosed { open_pos: BytePos, expected: DelimKind },
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -66,13 +180,14 @@ impl<'a> From<&'a Token> for TokenType {
 impl<'a, I: Input> Iterator for Lexer<'a, I> {
     type Item = TokenAndSpan;
     fn next(&mut self) -> Option<Self::Item> {
+        let is_first_token = self.state.is_first;
         self.state.had_line_break = self.state.is_first;
-        self.state.is_first = false;
+
+        let pos_before_trivia = self.cur_pos();
+        let mut trivia_skipped = false;
 
         // skip spaces before getting next character, if we are allowed to.
         if self.state.can_skip_space() {
-            let start = self.cur_pos();
-
             match self.skip_space() {
                 Err(err) => {
                     return Some(Token::Error(err)).map(|token| {
@@ -80,16 +195,38 @@ impl<'a, I: Input> Iterator for Lexer<'a, I> {
                         TokenAndSpan {
                             token,
                             had_line_break: self.had_line_break_before_last(),
-                            span: self.span(start),
+                            spacing: Spacing::Alone,
+                            span: self.span(pos_before_trivia),
                         }
                     });
                 }
                 _ => {}
             }
+
+            trivia_skipped = self.cur_pos() != pos_before_trivia;
         };
 
+        self.state.is_first = false;
+
         let start = self.cur_pos();
 
+        // `a?.b` (no trivia) is `Joint`; `a ? .b` is `Alone`. Inside
+        // `Type::Tpl`/other preserve-space contexts `skip_space` never ran
+        // above, so fall back to comparing raw byte positions instead.
+        let spacing = if is_first_token {
+            Spacing::Alone
+        } else if self.state.can_skip_space() {
+            if trivia_skipped {
+                Spacing::Alone
+            } else {
+                Spacing::Joint
+            }
+        } else if self.state.last_token_end == start {
+            Spacing::Joint
+        } else {
+            Spacing::Alone
+        };
+
         let res = if let Some(Type::Tpl {
             start: start_pos_of_tpl,
         }) = self.state.context.current()
@@ -106,13 +243,18 @@ impl<'a, I: Input> Iterator for Lexer<'a, I> {
 
         if let Some(ref token) = token {
             self.state.update(start, &token)
+        } else {
+            self.state.check_unclosed_delims(start);
         }
 
+        self.state.last_token_end = self.cur_pos();
+
         token.map(|token| {
             // Attatch span to token.
             TokenAndSpan {
                 token,
                 had_line_break: self.had_line_break_before_last(),
+                spacing,
                 span: self.span(start),
             }
         })
@@ -126,8 +268,14 @@ impl Default for State {
             octal_pos: None,
             is_first: true,
             had_line_break: false,
-            context: Context(smallvec![Type::BraceStmt]),
+            context: Context(smallvec![(Type::BraceStmt, BytePos(0))]),
             token_type: None,
+            unmatched_delims: Vec::new(),
+            last_token_end: BytePos(0),
+            capture_trivia: false,
+            leading_comments: HashMap::new(),
+            trailing_comments: HashMap::new(),
+            diagnostics: Vec::new(),
         }
     }
 }
@@ -148,6 +296,91 @@ impl State {
         }
     }
 
+    /// Drains delimiter-mismatch diagnostics collected since the last call.
+    /// The parser should call this after lexing to turn the raw positions
+    /// into user-facing errors.
+    pub fn take_unmatched_delims(&mut self) -> Vec<UnmatchedDelim> {
+        mem::take(&mut self.unmatched_delims)
+    }
+
+    /// Turns a pending `octal_pos` (if any) into a structured, recoverable
+    /// [`LexError`] with a machine-applicable fix, and clears `octal_pos`.
+    ///
+    /// `raw` is the literal's source text, e.g. `"0777"` or `"08"`.
+    /// Literals made up only of octal digits are rewritten with an explicit
+    /// `0o` prefix (`0777` -> `0o777`); ones containing `8`/`9`, which were
+    /// never valid octal and are instead parsed as legacy decimal, have
+    /// their leading zero stripped (`08` -> `8`).
+    pub fn check_legacy_octal(&mut self, span: Span, raw: &str) {
+        if self.octal_pos.take().is_none() {
+            return;
+        }
+
+        let is_octal = raw.len() > 1 && raw.as_bytes()[1..].iter().all(|b| (b'0'..=b'7').contains(b));
+        let replacement = if is_octal {
+            format!("0o{}", &raw[1..])
+        } else {
+            raw.trim_start_matches('0').to_owned()
+        };
+
+        self.diagnostics.push(LexError {
+            span,
+            message: "legacy octal literals are not allowed in strict mode",
+            suggestion: Some(LexSuggestion { span, replacement }),
+        });
+    }
+
+    /// Drains the recoverable diagnostics accumulated since the last call.
+    pub fn take_diagnostics(&mut self) -> Vec<LexError> {
+        mem::take(&mut self.diagnostics)
+    }
+
+    /// Records `comment` as leading trivia of the token that will start at
+    /// `token_start`. Called from `skip_space` while `capture_trivia` is
+    /// enabled, once the comment's kind (line / block / jsdoc) has been
+    /// classified from the skipped region.
+    pub fn record_leading_comment(&mut self, token_start: BytePos, comment: Comment) {
+        self.leading_comments
+            .entry(token_start)
+            .or_insert_with(Vec::new)
+            .push(comment);
+    }
+
+    /// Records `comment` as trailing trivia of the token that ended at
+    /// `token_end`.
+    pub fn record_trailing_comment(&mut self, token_end: BytePos, comment: Comment) {
+        self.trailing_comments
+            .entry(token_end)
+            .or_insert_with(Vec::new)
+            .push(comment);
+    }
+
+    /// Takes (and clears) the comments captured as leading trivia of the
+    /// token starting at `token_start`.
+    pub fn take_leading_comments(&mut self, token_start: BytePos) -> Vec<Comment> {
+        self.leading_comments.remove(&token_start).unwrap_or_default()
+    }
+
+    /// Takes (and clears) the comments captured as trailing trivia of the
+    /// token ending at `token_end`.
+    pub fn take_trailing_comments(&mut self, token_end: BytePos) -> Vec<Comment> {
+        self.trailing_comments.remove(&token_end).unwrap_or_default()
+    }
+
+    /// Reports every `(`, `{` or `${` opener still left on the context stack
+    /// once EOF is reached at `eof_pos`, e.g. a `{` that was never closed.
+    /// Unclosed template backticks are not reported.
+    pub fn check_unclosed_delims(&mut self, eof_pos: BytePos) {
+        // Skip the bottom-of-stack entry: it represents the top-level
+        // module/script scope, not a real `{` typed by the user.
+        for &(ty, open_pos) in self.context.0.iter().skip(1) {
+            if let Some(expected) = ty.delim_kind() {
+                self.unmatched_delims
+                    .push(UnmatchedDelim::Unclosed { open_pos, expected });
+            }
+        }
+    }
+
     fn update(&mut self, start: BytePos, next: &Token) {
         trace!(
             "updating state: next={:?}, had_line_break={} ",
@@ -159,6 +392,7 @@ impl State {
 
         self.is_expr_allowed = Self::is_expr_allowed_on_next(
             &mut self.context,
+            &mut self.unmatched_delims,
             prev,
             start,
             next,
@@ -171,6 +405,7 @@ impl State {
     /// `start`: start of newly produced token.
     fn is_expr_allowed_on_next(
         context: &mut Context,
+        unmatched_delims: &mut Vec<UnmatchedDelim>,
         prev: Option<TokenType>,
         start: BytePos,
         next: &Token,
@@ -188,12 +423,35 @@ impl State {
             // ported updateContext
             match *next {
                 tok!(')') | tok!('}') => {
+                    let found = if let tok!(')') = *next {
+                        DelimKind::Paren
+                    } else {
+                        DelimKind::Brace
+                    };
+
                     // TODO: Verify
                     if context.len() == 1 {
+                        // Every real opener has already been closed; this
+                        // closer doesn't match anything at all.
+                        unmatched_delims.push(UnmatchedDelim::Stray {
+                            close_pos: start,
+                            found,
+                        });
                         return true;
                     }
 
-                    let out = context.pop().unwrap();
+                    let (out, open_pos) = context.pop().unwrap();
+
+                    if let Some(expected) = out.delim_kind() {
+                        if expected != found {
+                            unmatched_delims.push(UnmatchedDelim::Mismatched {
+                                open_pos,
+                                close_pos: start,
+                                expected,
+                                found,
+                            });
+                        }
+                    }
 
                     // let a = function(){}
                     if out == Type::BraceStmt && context.current() == Some(Type::FnExpr) {
@@ -216,7 +474,7 @@ impl State {
                     if is_expr_allowed
                         && !context.is_brace_block(prev, had_line_break, is_expr_allowed)
                     {
-                        context.push(Type::FnExpr);
+                        context.push(Type::FnExpr, start);
                     }
                     return false;
                 }
@@ -254,26 +512,29 @@ impl State {
                     } else {
                         Type::BraceExpr
                     };
-                    context.push(next_ctxt);
+                    context.push(next_ctxt, start);
                     true
                 }
 
                 tok!("${") => {
-                    context.push(Type::TplQuasi);
+                    context.push(Type::TplQuasi, start);
                     return true;
                 }
 
                 tok!('(') => {
                     // if, for, with, while is statement
 
-                    context.push(match prev {
-                        Some(TokenType::Keyword(k)) => match k {
-                            If | With | While => Type::ParenStmt { is_for_loop: false },
-                            For => Type::ParenStmt { is_for_loop: true },
+                    context.push(
+                        match prev {
+                            Some(TokenType::Keyword(k)) => match k {
+                                If | With | While => Type::ParenStmt { is_for_loop: false },
+                                For => Type::ParenStmt { is_for_loop: true },
+                                _ => Type::ParenExpr,
+                            },
                             _ => Type::ParenExpr,
                         },
-                        _ => Type::ParenExpr,
-                    });
+                        start,
+                    );
                     return true;
                 }
 
@@ -285,7 +546,7 @@ impl State {
                     if let Some(Type::Tpl { .. }) = context.current() {
                         context.pop();
                     } else {
-                        context.push(Type::Tpl { start });
+                        context.push(Type::Tpl { start }, start);
                     }
                     return false;
                 }
@@ -298,8 +559,8 @@ impl State {
     }
 }
 
-#[derive(Debug, Default)]
-struct Context(SmallVec<[Type; 32]>);
+#[derive(Debug, Default, Clone)]
+struct Context(SmallVec<[(Type, BytePos); 32]>);
 impl Context {
     /// Returns true if following `LBrace` token is `block statement` according
     /// to  `ctx`, `prev`, `is_expr_allowed`.
@@ -355,17 +616,17 @@ impl Context {
     fn len(&self) -> usize {
         self.0.len()
     }
-    fn pop(&mut self) -> Option<Type> {
+    fn pop(&mut self) -> Option<(Type, BytePos)> {
         let opt = self.0.pop();
         trace!("context.pop({:?})", opt);
         opt
     }
     fn current(&self) -> Option<Type> {
-        self.0.last().cloned()
+        self.0.last().map(|&(t, _)| t)
     }
-    fn push(&mut self, t: Type) {
+    fn push(&mut self, t: Type, open_pos: BytePos) {
         trace!("context.push({:?})", t);
-        self.0.push(t);
+        self.0.push((t, open_pos));
     }
 }
 
@@ -394,3 +655,25 @@ enum Type {
     #[kind(is_expr)]
     FnExpr,
 }
+
+impl Type {
+    /// Delimiter kind this context expects to be closed with, if it's
+    /// closed by `)`/`}` at all. `Tpl` (closed by a backtick) and `FnExpr`
+    /// (a marker pushed on the `function` keyword, not on an opener) have no
+    /// delimiter of their own here.
+    fn delim_kind(&self) -> Option<DelimKind> {
+        match self {
+            Type::BraceStmt | Type::BraceExpr | Type::TplQuasi => Some(DelimKind::Brace),
+            Type::ParenStmt { .. } | Type::ParenExpr => Some(DelimKind::Paren),
+            Type::Tpl { .. } | Type::FnExpr => None,
+        }
+    }
+}
+
+/// A snapshot of the lexer sufficient to [`rewind`](Lexer::rewind) back to
+/// exactly where it was taken.
+#[derive(Debug, Clone)]
+pub(super) struct Checkpoint {
+    pub(super) state: State,
+    pub(super) pos: BytePos,
+}
@@ -0,0 +1,14 @@
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate smallvec;
+
+#[macro_use]
+mod token;
+mod lexer;
+
+pub use self::lexer::{
+    Comment, CommentKind, DelimKind, Input, LexError, LexSuggestion, Lexer, Spacing, StringInput,
+    UnmatchedDelim,
+};
+pub use self::token::{Token, TokenAndSpan};
@@ -0,0 +1,140 @@
+use crate::lexer::state::Spacing;
+use swc_common::Span;
+
+/// A hard lexer failure: the input couldn't be tokenized at all.
+///
+/// Distinct from the recoverable `LexError` diagnostics in `lexer::state`,
+/// which describe valid-but-deprecated syntax rather than a dead end.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    pub span: Span,
+    pub message: String,
+}
+
+/// The fixed set of words the lexer special-cases, including contextual
+/// ones like `of` that it needs to recognize while tokenizing, before the
+/// parser has any notion of statement context.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keyword {
+    Function,
+    If,
+    With,
+    While,
+    For,
+    Return,
+    Yield,
+    Else,
+    Let,
+    Const,
+    Var,
+    Of,
+}
+pub use self::Keyword::*;
+
+impl Keyword {
+    pub fn before_expr(self) -> bool {
+        match self {
+            Keyword::Return | Keyword::Yield | Keyword::Else => true,
+            _ => false,
+        }
+    }
+}
+
+/// A word-like token: either a generic identifier or one of the recognized
+/// `Keyword`s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Word {
+    Ident(String),
+    Keyword(Keyword),
+}
+pub use self::Word::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOpToken {
+    Lt,
+    Gt,
+}
+pub use self::BinOpToken::*;
+
+impl BinOpToken {
+    pub fn before_expr(self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Word(Word),
+    Dot,
+    Colon,
+    Semi,
+    Question,
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    DollarLBrace,
+    BackQuote,
+    PlusPlus,
+    MinusMinus,
+    BinOp(BinOpToken),
+    Num { raw: String },
+    Template { raw: String },
+    Error(Error),
+}
+pub use self::Token::*;
+
+impl Token {
+    pub fn before_expr(&self) -> bool {
+        match self {
+            Token::Dot | Token::RParen | Token::Template { .. } | Token::Num { .. } => false,
+            Token::Colon | Token::LBrace | Token::Semi => true,
+            Token::BinOp(op) => op.before_expr(),
+            Token::Word(Word::Keyword(k)) => k.before_expr(),
+            _ => false,
+        }
+    }
+}
+
+/// A single token together with its span, whether a line break preceded it,
+/// and its [`Spacing`] relative to the previous token.
+#[derive(Debug, Clone)]
+pub struct TokenAndSpan {
+    pub token: Token,
+    pub had_line_break: bool,
+    pub spacing: Spacing,
+    pub span: Span,
+}
+
+macro_rules! tok {
+    ('(') => {
+        LParen
+    };
+    (')') => {
+        RParen
+    };
+    ('{') => {
+        LBrace
+    };
+    ('}') => {
+        RBrace
+    };
+    ('`') => {
+        BackQuote
+    };
+    ("${") => {
+        DollarLBrace
+    };
+    ("++") => {
+        PlusPlus
+    };
+    ("--") => {
+        MinusMinus
+    };
+    ("function") => {
+        Word(Keyword(Function))
+    };
+    ("of") => {
+        Word(Keyword(Of))
+    };
+}